@@ -1,9 +1,12 @@
 use crate::{Driver, Status};
+use async_compression::tokio::bufread::{BrotliDecoder, GzipDecoder, ZlibDecoder};
+use futures_util::{stream, StreamExt};
 use hyper::{
-    body::{self, Bytes},
+    body::{Bytes, HttpBody},
     client::{Client, HttpConnector},
+    header::{HeaderValue, CONTENT_ENCODING, CONTENT_LENGTH},
     http::response::Parts,
-    Body, Request as HyperRequest,
+    Body, Request as HyperRequest, Response as HyperResponse, StatusCode,
 };
 use hyper_rustls::{ConfigBuilderExt, HttpsConnector, HttpsConnectorBuilder};
 use log::{debug, info, warn};
@@ -13,18 +16,44 @@ use noun::{
     convert::{self, IntoNoun, TryFromNoun, TryIntoNoun},
     Noun, Rc,
 };
-use rustls::ClientConfig;
-use std::collections::HashMap;
+use rustls::{Certificate, ClientConfig, PrivateKey, RootCertStore};
+use std::{collections::HashMap, io, sync::Arc, time::Duration};
 use tokio::{
-    sync::mpsc::{Receiver, Sender},
+    io::{AsyncRead, AsyncReadExt, BufReader},
+    sync::mpsc::{self, Receiver, Sender, UnboundedReceiver, UnboundedSender},
     task::JoinHandle,
 };
+use tokio_util::io::StreamReader;
+
+/// Default timeout applied to a request that doesn't specify its own, covering everything from
+/// connecting through receiving the response headers.
+const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Default timeout applied to each wait for the next chunk of a response body. It bounds the gap
+/// between chunks, not the time the whole body takes: a server that keeps sending a chunk within
+/// every interval is never cut off.
+const DEFAULT_BODY_TIMEOUT: Duration = Duration::from_secs(30);
 
 /// An HTTP request.
 #[derive(Debug)]
 struct Request {
     req_num: u64,
     req: HyperRequest<Body>,
+    /// Overrides the driver's default request timeout when set.
+    timeout: Option<Duration>,
+    /// Pins the request to HTTP/1.1, bypassing ALPN negotiation of HTTP/2. Useful for servers
+    /// that misbehave when a client offers to multiplex.
+    pin_http1: bool,
+    /// Requests that the response be streamed as chunked noun events rather than buffered into
+    /// a single response noun, regardless of its size.
+    stream: bool,
+    /// Skips transparent decompression, delivering the response body exactly as the server
+    /// sent it (still `Content-Encoding`-tagged) even if it's gzip/deflate/br compressed.
+    raw_body: bool,
+    /// Overrides the driver's default streaming threshold (in bytes) when set, mirroring
+    /// `timeout` above. Irrelevant when `stream` is already true, since that skips buffering
+    /// entirely.
+    stream_threshold: Option<u64>,
 }
 
 impl TryFromNoun<Rc<Noun>> for Request {
@@ -34,12 +63,41 @@ impl TryFromNoun<Rc<Noun>> for Request {
         }
 
         if let Noun::Cell(req) = &*req {
-            let [req_num, method, uri, headers, body] =
-                req.as_list::<5>().ok_or(convert::Error::MissingValue)?;
-            if let (Noun::Atom(req_num), Noun::Atom(method), Noun::Atom(uri), mut headers, body) =
-                (&*req_num, &*method, &*uri, headers, body)
+            let [req_num, method, uri, headers, body, timeout, pin_http1, stream, raw_body, stream_threshold] =
+                req.as_list::<10>().ok_or(convert::Error::MissingValue)?;
+            if let (
+                Noun::Atom(req_num),
+                Noun::Atom(method),
+                Noun::Atom(uri),
+                mut headers,
+                body,
+                Noun::Atom(timeout),
+                Noun::Atom(pin_http1),
+                Noun::Atom(stream),
+                Noun::Atom(raw_body),
+                Noun::Atom(stream_threshold),
+            ) = (
+                &*req_num, &*method, &*uri, headers, body, &*timeout, &*pin_http1, &*stream,
+                &*raw_body, &*stream_threshold,
+            )
             {
                 let req_num = req_num.as_u64().ok_or(convert::Error::AtomToUint)?;
+                // A timeout of 0 means "use the driver's default timeout".
+                let timeout = match timeout.as_u64().ok_or(convert::Error::AtomToUint)? {
+                    0 => None,
+                    ms => Some(Duration::from_millis(ms)),
+                };
+                // Loobean: 0 is true (yes, pin to HTTP/1.1), 1 is false.
+                let pin_http1 = pin_http1.as_u64().ok_or(convert::Error::AtomToUint)? == 0;
+                // Loobean: 0 is true (yes, stream the response), 1 is false.
+                let stream = stream.as_u64().ok_or(convert::Error::AtomToUint)? == 0;
+                // Loobean: 0 is true (yes, skip decompression), 1 is false.
+                let raw_body = raw_body.as_u64().ok_or(convert::Error::AtomToUint)? == 0;
+                // A stream_threshold of 0 means "use the driver's default threshold".
+                let stream_threshold = match stream_threshold.as_u64().ok_or(convert::Error::AtomToUint)? {
+                    0 => None,
+                    bytes => Some(bytes),
+                };
 
                 let mut req = HyperRequest::builder()
                     .method(atom_as_str(method)?)
@@ -91,7 +149,15 @@ impl TryFromNoun<Rc<Noun>> for Request {
                     .body(body)
                     .map_err(|_| convert::Error::ImplType)?;
 
-                Ok(Self { req_num, req })
+                Ok(Self {
+                    req_num,
+                    req,
+                    timeout,
+                    pin_http1,
+                    stream,
+                    raw_body,
+                    stream_threshold,
+                })
             } else {
                 Err(convert::Error::UnexpectedCell)
             }
@@ -101,6 +167,30 @@ impl TryFromNoun<Rc<Noun>> for Request {
     }
 }
 
+/// Encodes a header map into the `[[key val] [key val] ... 0]` noun layout shared by every
+/// response shape below.
+fn encode_headers(headers: &hyper::HeaderMap) -> Rc<Noun> {
+    let null = Atom::null().into_rc_noun();
+    let mut headers_cell = null.clone();
+    for key in headers.keys().map(|k| k.as_str()) {
+        let vals = headers.get_all(key);
+        let key = Atom::from(key).into_rc_noun();
+        for val in vals {
+            let val = match val.to_str() {
+                Ok(val) => Atom::from(val).into_rc_noun(),
+                Err(_) => {
+                    warn!(target: "io-drivers:http:client", "skipping header value that isn't valid UTF-8");
+                    continue;
+                }
+            };
+            headers_cell =
+                Cell::from([Cell::from([key.clone(), val]).into_rc_noun(), headers_cell])
+                    .into_rc_noun();
+        }
+    }
+    headers_cell
+}
+
 /// An HTTP response.
 struct Response {
     req_num: u64,
@@ -116,24 +206,7 @@ impl TryIntoNoun<Noun> for Response {
         let status = Atom::from(self.parts.status.as_u16()).into_rc_noun();
         let null = Atom::null().into_rc_noun();
 
-        let headers = {
-            let mut headers_cell = null.clone();
-            let headers = &self.parts.headers;
-            for key in headers.keys().map(|k| k.as_str()) {
-                let vals = headers.get_all(key);
-                let key = Atom::from(key).into_rc_noun();
-                for val in vals {
-                    let val = match val.to_str() {
-                        Ok(val) => Atom::from(val).into_rc_noun(),
-                        Err(_) => todo!("handle ToStrError"),
-                    };
-                    headers_cell =
-                        Cell::from([Cell::from([key.clone(), val]).into_rc_noun(), headers_cell])
-                            .into_rc_noun();
-                }
-            }
-            headers_cell
-        };
+        let headers = encode_headers(&self.parts.headers);
 
         let body = {
             let body = self.body.to_vec();
@@ -150,11 +223,248 @@ impl TryIntoNoun<Noun> for Response {
     }
 }
 
+/// One event in a streamed response, emitted instead of a single combined `Response` noun so
+/// Arvo can start processing a large body before it's finished arriving.
+enum StreamEvent {
+    /// `[%response-head req_num status headers]`, sent once at the start of the stream.
+    Head {
+        req_num: u64,
+        status: u16,
+        headers: Rc<Noun>,
+    },
+    /// `[%response-chunk req_num chunk-len chunk]`, sent once per chunk yielded by the body.
+    Chunk { req_num: u64, chunk: Bytes },
+    /// `[%response-done req_num]`, sent once the body is exhausted successfully.
+    Done { req_num: u64 },
+    /// `[%response-failed req_num]`, sent instead of `response-done` when the body couldn't be
+    /// read to completion, so Arvo can tell a truncated download apart from a successful one.
+    Failed { req_num: u64 },
+}
+
+impl IntoNoun<Noun> for StreamEvent {
+    fn into_noun(self) -> Noun {
+        let req_num_noun = |req_num: u64| Atom::from(req_num).into_rc_noun();
+        match self {
+            Self::Head {
+                req_num,
+                status,
+                headers,
+            } => Cell::from([
+                Atom::from("response-head").into_rc_noun(),
+                req_num_noun(req_num),
+                Atom::from(status).into_rc_noun(),
+                headers,
+            ])
+            .into_noun(),
+            Self::Chunk { req_num, chunk } => {
+                let chunk_len = Atom::from(chunk.len());
+                let chunk = Atom::from(chunk.to_vec());
+                Cell::from([
+                    Atom::from("response-chunk").into_rc_noun(),
+                    req_num_noun(req_num),
+                    Cell::from([chunk_len, chunk]).into_rc_noun(),
+                ])
+                .into_noun()
+            }
+            Self::Done { req_num } => Cell::from([
+                Atom::from("response-done").into_rc_noun(),
+                req_num_noun(req_num),
+            ])
+            .into_noun(),
+            Self::Failed { req_num } => Cell::from([
+                Atom::from("response-failed").into_rc_noun(),
+                req_num_noun(req_num),
+            ])
+            .into_noun(),
+        }
+    }
+}
+
+/// Default value of `HttpClient::default_stream_threshold`: response bodies at or above this
+/// size are streamed as chunked noun events instead of being buffered into a single `Response`
+/// noun, regardless of whether the request asked for streaming explicitly. Overridable per
+/// request via `Request::stream_threshold`.
+const STREAM_THRESHOLD: u64 = 1024 * 1024;
+
+/// TLS configuration for the HTTP client driver, layered on top of the native root store.
+#[derive(Default)]
+pub struct TlsConfig {
+    /// Additional PEM-encoded root certificates, e.g. to pin a private CA.
+    extra_roots: Vec<u8>,
+    /// A PEM-encoded certificate chain and private key, presented during the handshake for
+    /// servers that require mutual TLS.
+    client_cert: Option<(Vec<u8>, Vec<u8>)>,
+    /// Skips server certificate verification entirely. Only ever appropriate for local
+    /// development against a self-signed endpoint; never enable this in production.
+    insecure: bool,
+}
+
+impl TlsConfig {
+    /// Trusts `pem`-encoded certificates in addition to the native root store.
+    pub fn with_extra_roots(mut self, pem: Vec<u8>) -> Self {
+        self.extra_roots = pem;
+        self
+    }
+
+    /// Presents `cert_pem`/`key_pem` (both PEM-encoded) as a client certificate, for APIs that
+    /// require mutual TLS.
+    pub fn with_client_cert(mut self, cert_pem: Vec<u8>, key_pem: Vec<u8>) -> Self {
+        self.client_cert = Some((cert_pem, key_pem));
+        self
+    }
+
+    /// Skips server certificate verification entirely. Only for local testing against a
+    /// self-signed endpoint.
+    pub fn insecure(mut self) -> Self {
+        self.insecure = true;
+        self
+    }
+
+    /// Builds the `rustls::ClientConfig` the connector will use. Only the native root store is
+    /// allowed to degrade gracefully (a container without a CA bundle still gets a usable,
+    /// if empty, config); malformed custom CA/client cert/key material supplied via
+    /// `with_extra_roots`/`with_client_cert` is rejected with an error instead of being
+    /// silently dropped, since a quietly-ignored mTLS config is a security footgun.
+    fn build(&self) -> Result<ClientConfig, TlsConfigError> {
+        let mut roots = RootCertStore::empty();
+        match rustls_native_certs::load_native_certs() {
+            Ok(certs) => {
+                for cert in certs {
+                    let _ = roots.add(&Certificate(cert.0));
+                }
+            }
+            Err(err) => {
+                warn!(target: "io-drivers:http:client", "failed to load native root certificates, falling back to whatever roots are otherwise configured: {}", err);
+            }
+        }
+        if !self.extra_roots.is_empty() {
+            for cert in rustls_pemfile::certs(&mut io::Cursor::new(&self.extra_roots))
+                .map_err(TlsConfigError::ExtraRoots)?
+            {
+                roots.add(&Certificate(cert)).map_err(|err| {
+                    TlsConfigError::ExtraRoots(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        err.to_string(),
+                    ))
+                })?;
+            }
+        }
+
+        let client_auth = self
+            .client_cert
+            .as_ref()
+            .map(|(cert_pem, key_pem)| {
+                let certs = rustls_pemfile::certs(&mut io::Cursor::new(cert_pem))
+                    .map_err(TlsConfigError::ClientCertChain)?
+                    .into_iter()
+                    .map(Certificate)
+                    .collect::<Vec<_>>();
+                let key = rustls_pemfile::pkcs8_private_keys(&mut io::Cursor::new(key_pem))
+                    .map_err(TlsConfigError::ClientPrivateKey)?
+                    .into_iter()
+                    .next()
+                    .map(PrivateKey)
+                    .ok_or(TlsConfigError::MissingClientPrivateKey)?;
+                Ok((certs, key))
+            })
+            .transpose()?;
+
+        let builder = ClientConfig::builder().with_safe_defaults();
+        if self.insecure {
+            let builder = builder.with_custom_certificate_verifier(Arc::new(InsecureCertVerifier));
+            match client_auth {
+                Some((certs, key)) => builder
+                    .with_client_auth_cert(certs, key)
+                    .map_err(TlsConfigError::ClientAuth),
+                None => Ok(builder.with_no_client_auth()),
+            }
+        } else {
+            let builder = builder.with_root_certificates(roots);
+            match client_auth {
+                Some((certs, key)) => builder
+                    .with_client_auth_cert(certs, key)
+                    .map_err(TlsConfigError::ClientAuth),
+                None => Ok(builder.with_no_client_auth()),
+            }
+        }
+    }
+}
+
+/// An error building a `rustls::ClientConfig` from a `TlsConfig`. Never raised by native root
+/// loading, which degrades to an empty/partial root store instead; only raised for malformed
+/// data the caller explicitly supplied.
+#[derive(Debug)]
+pub enum TlsConfigError {
+    /// Failed to parse the PEM passed to `TlsConfig::with_extra_roots`, or the root store
+    /// rejected a certificate in it.
+    ExtraRoots(io::Error),
+    /// Failed to parse the certificate chain PEM passed to `TlsConfig::with_client_cert`.
+    ClientCertChain(io::Error),
+    /// Failed to parse the private key PEM passed to `TlsConfig::with_client_cert`.
+    ClientPrivateKey(io::Error),
+    /// The private key PEM passed to `TlsConfig::with_client_cert` contained no PKCS#8 keys.
+    MissingClientPrivateKey,
+    /// rustls rejected the parsed client certificate/key pair.
+    ClientAuth(rustls::Error),
+}
+
+impl std::fmt::Display for TlsConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ExtraRoots(err) => write!(f, "failed to parse extra root certificates: {}", err),
+            Self::ClientCertChain(err) => {
+                write!(f, "failed to parse client certificate chain: {}", err)
+            }
+            Self::ClientPrivateKey(err) => write!(f, "failed to parse client private key: {}", err),
+            Self::MissingClientPrivateKey => {
+                write!(f, "no private key found in client key PEM")
+            }
+            Self::ClientAuth(err) => write!(f, "failed to configure client auth certificate: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for TlsConfigError {}
+
+/// Accepts any server certificate without verification. Only ever wired in when
+/// `TlsConfig::insecure` is set, for local development against a self-signed endpoint.
+struct InsecureCertVerifier;
+
+impl rustls::client::ServerCertVerifier for InsecureCertVerifier {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &Certificate,
+        _intermediates: &[Certificate],
+        _server_name: &rustls::ServerName,
+        _scts: &mut dyn Iterator<Item = &[u8]>,
+        _ocsp_response: &[u8],
+        _now: std::time::SystemTime,
+    ) -> Result<rustls::client::ServerCertVerified, rustls::Error> {
+        Ok(rustls::client::ServerCertVerified::assertion())
+    }
+}
+
 /// The HTTP client driver.
 pub struct HttpClient {
+    /// Negotiates HTTP/2 via ALPN when the server supports it, falling back to HTTP/1.1.
     hyper: Client<HttpsConnector<HttpConnector>, Body>,
+    /// Pinned to HTTP/1.1, for requests that opt out of HTTP/2 negotiation.
+    hyper_http1: Client<HttpsConnector<HttpConnector>, Body>,
     /// Map from request number to request task. Must only be accessed from a single task.
     inflight_req: HashMap<u64, JoinHandle<()>>,
+    /// Default timeout applied to a request that doesn't override it.
+    default_timeout: Duration,
+    /// Timeout applied to each wait for the next chunk of a response body, not to the body as a
+    /// whole.
+    body_timeout: Duration,
+    /// Default streaming threshold (in bytes) applied to a request that doesn't override it: once
+    /// this many body bytes have been received, the response is streamed instead of buffered.
+    default_stream_threshold: u64,
+    /// Used by request tasks to signal that they've finished and can be evicted from
+    /// `inflight_req`.
+    cleanup_tx: UnboundedSender<u64>,
+    /// Taken by `run` so the driver's main loop can select on it alongside `req_rx`.
+    cleanup_rx: Option<UnboundedReceiver<u64>>,
 }
 
 impl HttpClient {
@@ -172,49 +482,48 @@ impl HttpClient {
 
         let req_num = req.req_num;
         debug!(target: "io-drivers:http:client", "request number = {}", req_num);
+        let timeout = req.timeout.unwrap_or(self.default_timeout);
+        let body_timeout = self.body_timeout;
+        let stream_threshold = req.stream_threshold.unwrap_or(self.default_stream_threshold);
         let task = {
-            let hyper = self.hyper.clone();
+            let hyper = if req.pin_http1 {
+                self.hyper_http1.clone()
+            } else {
+                self.hyper.clone()
+            };
+            let cleanup_tx = self.cleanup_tx.clone();
             let task = tokio::spawn(async move {
-                let resp = match hyper.request(req.req).await {
-                    Ok(resp) => resp,
-                    Err(err) => {
+                let resp = match tokio::time::timeout(timeout, hyper.request(req.req)).await {
+                    Ok(Ok(resp)) => resp,
+                    Ok(Err(err)) => {
                         warn!(target: "io-drivers:http:client", "failed to send request #{}: {}", req_num, err);
+                        let _ = cleanup_tx.send(req_num);
                         return;
                     }
-                };
-                debug!(target: "io-drivers:http:client", "response to request {} = {:?}", req_num, resp);
-
-                let (parts, body) = resp.into_parts();
-
-                let body = match body::to_bytes(body).await {
-                    Ok(body) => body,
-                    Err(err) => {
-                        warn!(target: "io-drivers:http:client", "failed to receive entire body of request #{}: {}", req_num, err);
+                    Err(_) => {
+                        warn!(target: "io-drivers:http:client", "request #{} timed out after {:?}", req_num, timeout);
+                        send_timeout_response(req_num, &resp_tx).await;
+                        let _ = cleanup_tx.send(req_num);
                         return;
                     }
                 };
-                debug!(target: "io-drivers:http:client", "response body to request {} = {:?}", req_num, body);
+                debug!(target: "io-drivers:http:client", "response to request {} = {:?}", req_num, resp);
 
+                let (parts, body) = resp.into_parts();
                 info!(target: "io-drivers:http:client", "received status {} in response to request #{}", parts.status.as_u16(), req_num);
 
-                let resp = match (Response {
-                    req_num: req.req_num,
+                send_response(
+                    req_num,
                     parts,
                     body,
-                })
-                .try_into_noun()
-                {
-                    Ok(resp) => resp,
-                    Err(err) => {
-                        warn!(target: "io-drivers:http:client", "failed to convert response to request #{} into noun: {:?}", req_num, err);
-                        return;
-                    }
-                };
-                if let Err(_resp) = resp_tx.send(resp).await {
-                    warn!(target: "io-drivers:http:client", "failed to send response to request #{} to stdout task", req_num);
-                } else {
-                    info!(target: "io-drivers:http:client", "sent response to request #{} to stdout task", req_num);
-                }
+                    body_timeout,
+                    stream_threshold,
+                    req.raw_body,
+                    req.stream,
+                    &resp_tx,
+                )
+                .await;
+                let _ = cleanup_tx.send(req_num);
             });
             debug!(target: "io-drivers:http:client", "spawned task to handle request #{}", req_num);
             task
@@ -241,50 +550,338 @@ impl HttpClient {
     }
 }
 
-impl Driver for HttpClient {
-    fn new() -> Self {
-        let tls = ClientConfig::builder()
-            .with_safe_defaults()
-            .with_native_roots()
-            .with_no_client_auth();
+/// Builds and sends a synthetic 504 response noun for a request that timed out, so Arvo can
+/// react deterministically instead of waiting on a reply that will never arrive.
+async fn send_timeout_response(req_num: u64, resp_tx: &Sender<Noun>) {
+    let parts = HyperResponse::builder()
+        .status(StatusCode::GATEWAY_TIMEOUT)
+        .body(())
+        .expect("build response")
+        .into_parts()
+        .0;
+    let resp = match (Response {
+        req_num,
+        parts,
+        body: Bytes::new(),
+    })
+    .try_into_noun()
+    {
+        Ok(resp) => resp,
+        Err(err) => {
+            warn!(target: "io-drivers:http:client", "failed to convert timeout response for request #{} into noun: {:?}", req_num, err);
+            return;
+        }
+    };
+    if resp_tx.send(resp).await.is_err() {
+        warn!(target: "io-drivers:http:client", "failed to send timeout response for request #{} to stdout task", req_num);
+    }
+}
+
+/// Transparently decompresses `body` according to `parts`' `Content-Encoding` header, stripping
+/// the header and fixing up `Content-Length` on success. Unrecognized or absent encodings are
+/// passed through unchanged.
+async fn decompress_body(parts: &mut Parts, body: Bytes) -> Bytes {
+    let encoding = match parts.headers.get(CONTENT_ENCODING).and_then(|val| val.to_str().ok()) {
+        Some("gzip") => "gzip",
+        Some("deflate") => "deflate",
+        Some("br") => "br",
+        _ => return body,
+    };
 
+    let original = body.clone();
+    let reader = BufReader::new(StreamReader::new(stream::once(async move {
+        Ok::<_, io::Error>(body)
+    })));
+
+    let mut decoded = Vec::new();
+    let result = match encoding {
+        "gzip" => GzipDecoder::new(reader).read_to_end(&mut decoded).await,
+        "deflate" => ZlibDecoder::new(reader).read_to_end(&mut decoded).await,
+        "br" => BrotliDecoder::new(reader).read_to_end(&mut decoded).await,
+        _ => unreachable!(),
+    };
+
+    match result {
+        Ok(_) => {
+            parts.headers.remove(CONTENT_ENCODING);
+            parts.headers.insert(
+                CONTENT_LENGTH,
+                HeaderValue::from_str(&decoded.len().to_string()).expect("valid header value"),
+            );
+            Bytes::from(decoded)
+        }
+        Err(err) => {
+            warn!(target: "io-drivers:http:client", "failed to decompress {} body: {}", encoding, err);
+            original
+        }
+    }
+}
+
+/// Buffers the response body up to `stream_threshold`, then either finishes converting it into
+/// a single `Response` noun (the common case) or, if the body is still going, hands off to
+/// `stream_response` for the rest. A declared `Content-Length` isn't enough to make this call
+/// upfront since it's absent for chunked-transfer responses -- exactly the "large download"
+/// case streaming exists for -- so the decision is made against bytes actually received.
+/// `force_stream` short-circuits straight to streaming when the request asked for it explicitly.
+async fn send_response(
+    req_num: u64,
+    mut parts: Parts,
+    mut body: Body,
+    body_timeout: Duration,
+    stream_threshold: u64,
+    raw_body: bool,
+    force_stream: bool,
+    resp_tx: &Sender<Noun>,
+) {
+    let mut buffered = Vec::new();
+    if !force_stream {
+        loop {
+            if buffered.len() as u64 >= stream_threshold {
+                break;
+            }
+            match tokio::time::timeout(body_timeout, body.data()).await {
+                Ok(Some(Ok(chunk))) => buffered.extend_from_slice(&chunk),
+                Ok(Some(Err(err))) => {
+                    warn!(target: "io-drivers:http:client", "failed to receive entire body of request #{}: {}", req_num, err);
+                    return;
+                }
+                Ok(None) => {
+                    let body = Bytes::from(buffered);
+                    debug!(target: "io-drivers:http:client", "response body to request {} = {:?}", req_num, body);
+                    let body = if raw_body {
+                        body
+                    } else {
+                        decompress_body(&mut parts, body).await
+                    };
+
+                    let resp = match (Response {
+                        req_num,
+                        parts,
+                        body,
+                    })
+                    .try_into_noun()
+                    {
+                        Ok(resp) => resp,
+                        Err(err) => {
+                            warn!(target: "io-drivers:http:client", "failed to convert response to request #{} into noun: {:?}", req_num, err);
+                            return;
+                        }
+                    };
+                    if resp_tx.send(resp).await.is_err() {
+                        warn!(target: "io-drivers:http:client", "failed to send response to request #{} to stdout task", req_num);
+                    } else {
+                        info!(target: "io-drivers:http:client", "sent response to request #{} to stdout task", req_num);
+                    }
+                    return;
+                }
+                Err(_) => {
+                    warn!(target: "io-drivers:http:client", "body of request #{} timed out after {:?}", req_num, body_timeout);
+                    send_timeout_response(req_num, resp_tx).await;
+                    return;
+                }
+            }
+        }
+    }
+
+    debug!(target: "io-drivers:http:client", "request #{} crossed the streaming threshold, switching to streamed chunks", req_num);
+    stream_response(req_num, parts, Bytes::from(buffered), body, body_timeout, raw_body, resp_tx).await;
+}
+
+/// Streams the response body in as a `response-head` noun, followed by a `response-chunk` noun
+/// per chunk read from the body, followed by a final `response-done` noun on success or
+/// `response-failed` if the body couldn't be read to completion (a transport error or a body
+/// timeout), so Arvo can tell a truncated download apart from a successful one. `prefix` is
+/// whatever `send_response` had already buffered before deciding to stream; it's replayed as the
+/// start of the body. Unless `raw_body` is set, the body is decompressed chunk-by-chunk as it
+/// arrives rather than requiring the whole thing to be buffered first.
+async fn stream_response(
+    req_num: u64,
+    mut parts: Parts,
+    prefix: Bytes,
+    body: Body,
+    body_timeout: Duration,
+    raw_body: bool,
+    resp_tx: &Sender<Noun>,
+) {
+    let encoding = if raw_body {
+        None
+    } else {
+        match parts.headers.get(CONTENT_ENCODING).and_then(|val| val.to_str().ok()) {
+            Some("gzip") => Some("gzip"),
+            Some("deflate") => Some("deflate"),
+            Some("br") => Some("br"),
+            _ => None,
+        }
+    };
+    if encoding.is_some() {
+        parts.headers.remove(CONTENT_ENCODING);
+        parts.headers.remove(CONTENT_LENGTH);
+    }
+
+    let head = StreamEvent::Head {
+        req_num,
+        status: parts.status.as_u16(),
+        headers: encode_headers(&parts.headers),
+    };
+    if resp_tx.send(head.into_noun()).await.is_err() {
+        warn!(target: "io-drivers:http:client", "failed to send response head of request #{} to stdout task", req_num);
+        return;
+    }
+
+    let raw_chunks = stream::once(async move { Ok::<_, io::Error>(prefix) }).chain(stream::unfold(
+        body,
+        move |mut body| async move {
+            match tokio::time::timeout(body_timeout, body.data()).await {
+                Ok(Some(Ok(chunk))) => Some((Ok(chunk), body)),
+                Ok(Some(Err(err))) => {
+                    warn!(target: "io-drivers:http:client", "failed to receive a chunk of request #{}: {}", req_num, err);
+                    Some((Err(io::Error::new(io::ErrorKind::Other, err.to_string())), body))
+                }
+                Ok(None) => None,
+                Err(_) => {
+                    warn!(target: "io-drivers:http:client", "chunk of request #{} timed out after {:?}", req_num, body_timeout);
+                    Some((
+                        Err(io::Error::new(io::ErrorKind::TimedOut, "body timed out")),
+                        body,
+                    ))
+                }
+            }
+        },
+    ));
+    let reader = BufReader::new(StreamReader::new(raw_chunks));
+
+    let completed = match encoding {
+        Some("gzip") => stream_chunks(GzipDecoder::new(reader), req_num, resp_tx).await,
+        Some("deflate") => stream_chunks(ZlibDecoder::new(reader), req_num, resp_tx).await,
+        Some("br") => stream_chunks(BrotliDecoder::new(reader), req_num, resp_tx).await,
+        None => stream_chunks(reader, req_num, resp_tx).await,
+    };
+
+    let done = if completed {
+        StreamEvent::Done { req_num }
+    } else {
+        StreamEvent::Failed { req_num }
+    };
+    if resp_tx.send(done.into_noun()).await.is_err() {
+        warn!(target: "io-drivers:http:client", "failed to send response-done of request #{} to stdout task", req_num);
+    } else if completed {
+        info!(target: "io-drivers:http:client", "finished streaming response to request #{}", req_num);
+    } else {
+        info!(target: "io-drivers:http:client", "streaming response to request #{} ended early", req_num);
+    }
+}
+
+/// Reads `reader` to completion, emitting a `response-chunk` noun per chunk read. Returns
+/// whether the body was read to completion (`Ok(0)`) rather than ending in an error, which
+/// covers both a transport failure and a decompression error -- the latter surfaces here as an
+/// `io::Error` from the decoder, same as any other read failure.
+async fn stream_chunks(mut reader: impl AsyncRead + Unpin, req_num: u64, resp_tx: &Sender<Noun>) -> bool {
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        match reader.read(&mut buf).await {
+            Ok(0) => return true,
+            Ok(n) => {
+                debug!(target: "io-drivers:http:client", "read chunk of {} bytes for request #{}", n, req_num);
+                let chunk = StreamEvent::Chunk {
+                    req_num,
+                    chunk: Bytes::copy_from_slice(&buf[..n]),
+                };
+                if resp_tx.send(chunk.into_noun()).await.is_err() {
+                    warn!(target: "io-drivers:http:client", "failed to send response chunk of request #{} to stdout task", req_num);
+                    return false;
+                }
+            }
+            Err(err) => {
+                warn!(target: "io-drivers:http:client", "failed to read body of request #{}: {}", req_num, err);
+                return false;
+            }
+        }
+    }
+}
+
+impl HttpClient {
+    /// Constructs the driver with a custom TLS configuration, e.g. to pin a custom CA, present
+    /// a client certificate for mutual TLS, or (for local development only) skip server
+    /// certificate verification entirely. `Driver::new` below just passes `TlsConfig::default()`,
+    /// which can never fail since it only exercises the native-root path.
+    pub fn with_tls_config(tls: TlsConfig) -> Result<Self, TlsConfigError> {
+        let mut h2_tls = tls.build()?;
+        // Offer both protocols during the handshake so the server picks the best one it
+        // supports; hyper-rustls switches the connector to h2 when it's negotiated.
+        h2_tls.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
         let https = HttpsConnectorBuilder::new()
-            .with_tls_config(tls)
+            .with_tls_config(h2_tls)
             .https_or_http()
             .enable_http1()
+            .enable_http2()
             .build();
-
         let hyper = Client::builder().build(https);
+
+        let https_http1 = HttpsConnectorBuilder::new()
+            .with_tls_config(tls.build()?)
+            .https_or_http()
+            .enable_http1()
+            .build();
+        let hyper_http1 = Client::builder().build(https_http1);
+
         let inflight_req = HashMap::new();
+        let (cleanup_tx, cleanup_rx) = mpsc::unbounded_channel();
         debug!(target: "io-drivers:http:client", "initialized driver");
-        Self {
+        Ok(Self {
             hyper,
+            hyper_http1,
             inflight_req,
-        }
+            default_timeout: DEFAULT_REQUEST_TIMEOUT,
+            body_timeout: DEFAULT_BODY_TIMEOUT,
+            default_stream_threshold: STREAM_THRESHOLD,
+            cleanup_tx,
+            cleanup_rx: Some(cleanup_rx),
+        })
+    }
+}
+
+impl Driver for HttpClient {
+    fn new() -> Self {
+        Self::with_tls_config(TlsConfig::default())
+            .expect("default TLS configuration only exercises the native-root path, which never fails")
     }
 
     fn run(mut self, mut req_rx: Receiver<Noun>, resp_tx: Sender<Noun>) -> JoinHandle<Status> {
+        let mut cleanup_rx = self.cleanup_rx.take().expect("cleanup channel");
         let task = tokio::spawn(async move {
-            while let Some(req) = req_rx.recv().await {
-                if let Noun::Cell(req) = req {
-                    let (tag, req) = req.into_parts();
-                    if let Noun::Atom(tag) = &*tag {
-                        if tag == "request" {
-                            self.send_request(req, resp_tx.clone());
-                        } else if tag == "cancel-request" {
-                            self.cancel_request(req);
-                        } else {
-                            if let Ok(tag) = tag.as_str() {
-                                warn!(target: "io-drivers:http:client", "ignoring request with unknown tag %{}", tag);
+            loop {
+                tokio::select! {
+                    req = req_rx.recv() => {
+                        let req = match req {
+                            Some(req) => req,
+                            None => break,
+                        };
+                        if let Noun::Cell(req) = req {
+                            let (tag, req) = req.into_parts();
+                            if let Noun::Atom(tag) = &*tag {
+                                if tag == "request" {
+                                    self.send_request(req, resp_tx.clone());
+                                } else if tag == "cancel-request" {
+                                    self.cancel_request(req);
+                                } else {
+                                    if let Ok(tag) = tag.as_str() {
+                                        warn!(target: "io-drivers:http:client", "ignoring request with unknown tag %{}", tag);
+                                    } else {
+                                        warn!(target: "io-drivers:http:client", "ignoring request with unknown tag");
+                                    }
+                                }
                             } else {
-                                warn!(target: "io-drivers:http:client", "ignoring request with unknown tag");
+                                warn!(target: "io-drivers:http:client", "ignoring request because the tag is a cell");
                             }
+                        } else {
+                            warn!(target: "io-drivers:http:client", "ignoring request because it's an atom");
+                        }
+                    }
+                    Some(req_num) = cleanup_rx.recv() => {
+                        if self.inflight_req.remove(&req_num).is_some() {
+                            debug!(target: "io-drivers:http:client", "evicted completed request #{} from request cache", req_num);
                         }
-                    } else {
-                        warn!(target: "io-drivers:http:client", "ignoring request because the tag is a cell");
                     }
-                } else {
-                    warn!(target: "io-drivers:http:client", "ignoring request because it's an atom");
                 }
             }
             for (req_num, task) in self.inflight_req {
@@ -305,6 +902,67 @@ impl Driver for HttpClient {
 mod tests {
     use super::*;
     use hyper::http::response;
+    use tokio::io::AsyncWriteExt;
+
+    #[test]
+    fn request_try_from_noun() {
+        let noun = Cell::from([
+            Atom::from(3u64).into_noun(),
+            Atom::from("POST").into_noun(),
+            Atom::from("http://example.com:8080/path").into_noun(),
+            Cell::from([
+                Cell::from([Atom::from("x-test"), Atom::from("value")]).into_noun(),
+                Atom::null().into_noun(),
+            ])
+            .into_noun(),
+            Cell::from([
+                Atom::null(),
+                Cell::from([Atom::from(5u8), Atom::from("hello")]).into_noun(),
+            ])
+            .into_noun(),
+            Atom::from(2_000u64).into_noun(), // timeout: 2s.
+            Atom::from(0u8).into_noun(),       // pin_http1: true.
+            Atom::from(1u8).into_noun(),       // stream: false.
+            Atom::from(0u8).into_noun(),       // raw_body: true.
+            Atom::from(4_096u64).into_noun(),  // stream_threshold: 4096 bytes.
+        ])
+        .into_rc_noun();
+
+        let req = Request::try_from_noun(noun).expect("try_from_noun");
+        assert_eq!(req.req_num, 3);
+        assert_eq!(req.req.method(), hyper::Method::POST);
+        assert_eq!(req.req.uri().path(), "/path");
+        assert_eq!(req.req.headers().get("x-test").expect("header present"), "value");
+        assert_eq!(req.timeout, Some(Duration::from_millis(2_000)));
+        assert!(req.pin_http1);
+        assert!(!req.stream);
+        assert!(req.raw_body);
+        assert_eq!(req.stream_threshold, Some(4_096));
+    }
+
+    #[test]
+    fn request_try_from_noun_zero_means_driver_defaults() {
+        let noun = Cell::from([
+            Atom::from(4u64).into_noun(),
+            Atom::from("GET").into_noun(),
+            Atom::from("http://example.com/").into_noun(),
+            Atom::null().into_noun(),
+            Atom::null().into_noun(),
+            Atom::from(0u8).into_noun(), // timeout: use driver default.
+            Atom::from(1u8).into_noun(), // pin_http1: false.
+            Atom::from(0u8).into_noun(), // stream: true.
+            Atom::from(1u8).into_noun(), // raw_body: false.
+            Atom::from(0u8).into_noun(), // stream_threshold: use driver default.
+        ])
+        .into_rc_noun();
+
+        let req = Request::try_from_noun(noun).expect("try_from_noun");
+        assert_eq!(req.timeout, None);
+        assert!(!req.pin_http1);
+        assert!(req.stream);
+        assert!(!req.raw_body);
+        assert_eq!(req.stream_threshold, None);
+    }
 
     #[test]
     fn response_into_noun() {
@@ -384,4 +1042,143 @@ mod tests {
             assert_eq!(noun, expected);
         }
     }
+
+    #[test]
+    fn stream_event_into_noun() {
+        let req_num = 9u64;
+
+        let head = StreamEvent::Head {
+            req_num,
+            status: 200,
+            headers: Atom::null().into_rc_noun(),
+        };
+        assert_eq!(
+            head.into_noun(),
+            Cell::from([
+                Atom::from("response-head").into_noun(),
+                Atom::from(req_num).into_noun(),
+                Atom::from(200u16).into_noun(),
+                Atom::null().into_noun(),
+            ])
+            .into_noun()
+        );
+
+        let chunk = StreamEvent::Chunk {
+            req_num,
+            chunk: Bytes::from("hello"),
+        };
+        assert_eq!(
+            chunk.into_noun(),
+            Cell::from([
+                Atom::from("response-chunk").into_noun(),
+                Atom::from(req_num).into_noun(),
+                Cell::from([Atom::from(5u8).into_noun(), Atom::from("hello").into_noun()]).into_noun(),
+            ])
+            .into_noun()
+        );
+
+        let done = StreamEvent::Done { req_num };
+        assert_eq!(
+            done.into_noun(),
+            Cell::from([Atom::from("response-done").into_noun(), Atom::from(req_num).into_noun()])
+                .into_noun()
+        );
+
+        let failed = StreamEvent::Failed { req_num };
+        assert_eq!(
+            failed.into_noun(),
+            Cell::from([
+                Atom::from("response-failed").into_noun(),
+                Atom::from(req_num).into_noun(),
+            ])
+            .into_noun()
+        );
+    }
+
+    async fn gzip(data: &[u8]) -> Vec<u8> {
+        use async_compression::tokio::write::GzipEncoder;
+        let mut encoder = GzipEncoder::new(Vec::new());
+        encoder.write_all(data).await.expect("write");
+        encoder.shutdown().await.expect("shutdown");
+        encoder.into_inner()
+    }
+
+    async fn deflate(data: &[u8]) -> Vec<u8> {
+        use async_compression::tokio::write::ZlibEncoder;
+        let mut encoder = ZlibEncoder::new(Vec::new());
+        encoder.write_all(data).await.expect("write");
+        encoder.shutdown().await.expect("shutdown");
+        encoder.into_inner()
+    }
+
+    async fn brotli(data: &[u8]) -> Vec<u8> {
+        use async_compression::tokio::write::BrotliEncoder;
+        let mut encoder = BrotliEncoder::new(Vec::new());
+        encoder.write_all(data).await.expect("write");
+        encoder.shutdown().await.expect("shutdown");
+        encoder.into_inner()
+    }
+
+    #[tokio::test]
+    async fn decompress_body_gzip() {
+        let original = b"the quick brown fox jumps over the lazy dog";
+        let compressed = gzip(original).await;
+
+        let (mut parts, _body) = response::Builder::new()
+            .header("content-encoding", "gzip")
+            .body(())
+            .expect("build response")
+            .into_parts();
+
+        let decoded = decompress_body(&mut parts, Bytes::from(compressed)).await;
+        assert_eq!(decoded, Bytes::from(original.to_vec()));
+        assert!(parts.headers.get(CONTENT_ENCODING).is_none());
+        assert_eq!(
+            parts.headers.get(CONTENT_LENGTH).unwrap().to_str().unwrap(),
+            original.len().to_string()
+        );
+    }
+
+    #[tokio::test]
+    async fn decompress_body_deflate() {
+        let original = b"the quick brown fox jumps over the lazy dog";
+        let compressed = deflate(original).await;
+
+        let (mut parts, _body) = response::Builder::new()
+            .header("content-encoding", "deflate")
+            .body(())
+            .expect("build response")
+            .into_parts();
+
+        let decoded = decompress_body(&mut parts, Bytes::from(compressed)).await;
+        assert_eq!(decoded, Bytes::from(original.to_vec()));
+    }
+
+    #[tokio::test]
+    async fn decompress_body_brotli() {
+        let original = b"the quick brown fox jumps over the lazy dog";
+        let compressed = brotli(original).await;
+
+        let (mut parts, _body) = response::Builder::new()
+            .header("content-encoding", "br")
+            .body(())
+            .expect("build response")
+            .into_parts();
+
+        let decoded = decompress_body(&mut parts, Bytes::from(compressed)).await;
+        assert_eq!(decoded, Bytes::from(original.to_vec()));
+    }
+
+    #[tokio::test]
+    async fn decompress_body_unrecognized_encoding_passes_through() {
+        let original = Bytes::from("not actually compressed");
+        let (mut parts, _body) = response::Builder::new()
+            .header("content-encoding", "identity")
+            .body(())
+            .expect("build response")
+            .into_parts();
+
+        let decoded = decompress_body(&mut parts, original.clone()).await;
+        assert_eq!(decoded, original);
+    }
 }
@@ -0,0 +1,495 @@
+use crate::{Driver, Status};
+use hyper::{
+    body::{self, Bytes},
+    header::{HeaderName, HeaderValue},
+    service::{make_service_fn, service_fn},
+    Body, HeaderMap, Method, Request as HyperRequest, Response as HyperResponse, Server,
+    StatusCode, Uri,
+};
+use log::{debug, info, warn};
+use noun::{
+    atom::Atom,
+    cell::Cell,
+    convert::{self, IntoNoun, TryFromNoun},
+    Noun, Rc,
+};
+use std::{
+    collections::HashMap,
+    convert::{Infallible, TryFrom},
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::{
+    sync::{
+        mpsc::{self, Receiver, Sender, UnboundedReceiver, UnboundedSender},
+        oneshot, Mutex,
+    },
+    task::JoinHandle,
+};
+
+/// Timeout applied while waiting for Arvo to reply to a forwarded request, mirroring the HTTP
+/// client driver's own request timeout so a stalled Arvo can't block a connection forever.
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// An inbound HTTP connection, converted into a noun for delivery to Arvo.
+struct Request {
+    req_num: u64,
+    method: Method,
+    uri: Uri,
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+impl IntoNoun<Noun> for Request {
+    /// Converts the request into a `[req_num method uri headers body]` noun, laid out like the
+    /// first five fields that `http::client::Request::try_from_noun` parses; the client's
+    /// trailing per-request options (timeout, pin_http1, stream, raw_body, stream_threshold) are
+    /// not included.
+    fn into_noun(self) -> Noun {
+        let req_num = Atom::from(self.req_num).into_rc_noun();
+        let method = Atom::from(self.method.as_str()).into_rc_noun();
+        let uri = Atom::from(self.uri.to_string()).into_rc_noun();
+        let null = Atom::null().into_rc_noun();
+
+        let headers = {
+            let mut headers_cell = null.clone();
+            for key in self.headers.keys().map(|k| k.as_str()) {
+                let vals = self.headers.get_all(key);
+                let key = Atom::from(key).into_rc_noun();
+                for val in vals {
+                    let val = match val.to_str() {
+                        Ok(val) => Atom::from(val).into_rc_noun(),
+                        Err(_) => {
+                            warn!(target: "io-drivers:http:server", "skipping header value that isn't valid UTF-8");
+                            continue;
+                        }
+                    };
+                    headers_cell =
+                        Cell::from([Cell::from([key.clone(), val]).into_rc_noun(), headers_cell])
+                            .into_rc_noun();
+                }
+            }
+            headers_cell
+        };
+
+        let body = if self.body.is_empty() {
+            null
+        } else {
+            let body_len = Atom::from(self.body.len());
+            let body = Atom::from(self.body.to_vec());
+            Cell::from([null, Cell::from([body_len, body]).into_rc_noun()]).into_rc_noun()
+        };
+
+        Cell::from([req_num, method, uri, headers, body]).into_noun()
+    }
+}
+
+/// An HTTP response sent back by Arvo in reply to a `Request` above.
+struct Response {
+    req_num: u64,
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+impl TryFromNoun<Rc<Noun>> for Response {
+    fn try_from_noun(resp: Rc<Noun>) -> Result<Self, convert::Error> {
+        fn atom_as_str(atom: &Atom) -> Result<&str, convert::Error> {
+            atom.as_str().map_err(|_| convert::Error::AtomToStr)
+        }
+
+        if let Noun::Cell(resp) = &*resp {
+            let [req_num, status, headers, body] =
+                resp.as_list::<4>().ok_or(convert::Error::MissingValue)?;
+            if let (Noun::Atom(req_num), Noun::Atom(status), mut headers, body) =
+                (&*req_num, &*status, headers, body)
+            {
+                let req_num = req_num.as_u64().ok_or(convert::Error::AtomToUint)?;
+                let status = status.as_u64().ok_or(convert::Error::AtomToUint)?;
+                let status = u16::try_from(status).map_err(|_| convert::Error::AtomToUint)?;
+                let status = StatusCode::from_u16(status).map_err(|_| convert::Error::ImplType)?;
+
+                let mut header_map = HeaderMap::new();
+                while let Noun::Cell(cell) = &*headers {
+                    let header = cell.head();
+                    if let Noun::Cell(header) = &*header {
+                        if let (Noun::Atom(key), Noun::Atom(val)) =
+                            (&*header.head(), &*header.tail())
+                        {
+                            let key = HeaderName::try_from(atom_as_str(key)?)
+                                .map_err(|_| convert::Error::ImplType)?;
+                            let val = HeaderValue::try_from(atom_as_str(val)?)
+                                .map_err(|_| convert::Error::ImplType)?;
+                            header_map.append(key, val);
+                        } else {
+                            return Err(convert::Error::UnexpectedCell);
+                        }
+                    } else {
+                        return Err(convert::Error::UnexpectedAtom);
+                    }
+                    headers = cell.tail();
+                }
+
+                let body = match &*body {
+                    Noun::Atom(_) => Bytes::new(),
+                    Noun::Cell(body) => {
+                        let [_null, _body_len, body] =
+                            body.as_list::<3>().ok_or(convert::Error::MissingValue)?;
+                        if let Noun::Atom(body) = &*body {
+                            Bytes::from(atom_as_str(body)?.as_bytes().to_vec())
+                        } else {
+                            return Err(convert::Error::UnexpectedCell);
+                        }
+                    }
+                };
+
+                Ok(Self {
+                    req_num,
+                    status,
+                    headers: header_map,
+                    body,
+                })
+            } else {
+                Err(convert::Error::UnexpectedCell)
+            }
+        } else {
+            Err(convert::Error::UnexpectedCell)
+        }
+    }
+}
+
+/// A request to bind a listener to some address.
+struct Listen {
+    addr: SocketAddr,
+}
+
+impl TryFromNoun<Rc<Noun>> for Listen {
+    fn try_from_noun(addr: Rc<Noun>) -> Result<Self, convert::Error> {
+        if let Noun::Atom(addr) = &*addr {
+            let addr = addr.as_str().map_err(|_| convert::Error::AtomToStr)?;
+            let addr = addr.parse().map_err(|_| convert::Error::ImplType)?;
+            Ok(Self { addr })
+        } else {
+            Err(convert::Error::UnexpectedCell)
+        }
+    }
+}
+
+/// Map from request number to the channel used to hand Arvo's reply back to the connection
+/// task that's waiting on it.
+type PendingConn = Arc<Mutex<HashMap<u64, oneshot::Sender<Response>>>>;
+
+/// Evicts `req_num` from `pending_conn` (via `cleanup_tx`) when dropped, including when
+/// `handle_conn`'s future is cancelled outright -- e.g. hyper drops it because the client
+/// disconnected while we were still waiting on Arvo -- which would otherwise leak the entry
+/// forever since none of `handle_conn`'s own cleanup code gets to run.
+struct PendingConnGuard {
+    req_num: u64,
+    cleanup_tx: UnboundedSender<u64>,
+}
+
+impl Drop for PendingConnGuard {
+    fn drop(&mut self) {
+        let _ = self.cleanup_tx.send(self.req_num);
+    }
+}
+
+/// The HTTP server driver.
+pub struct HttpServer {
+    /// Counter used to assign each inbound connection a unique request number.
+    next_req_num: Arc<AtomicU64>,
+    /// Connections awaiting a reply from Arvo. Shared with the listener's connection tasks.
+    pending_conn: PendingConn,
+    /// Used by connection tasks to signal that they're done waiting on Arvo and can be evicted
+    /// from `pending_conn`, including when the task is cancelled before it finishes normally.
+    cleanup_tx: UnboundedSender<u64>,
+    /// Taken by `run` so the driver's main loop can select on it alongside `req_rx`.
+    cleanup_rx: Option<UnboundedReceiver<u64>>,
+}
+
+impl HttpServer {
+    /// Binds a listener at `addr`, replacing whichever listener (if any) `run` previously spawned.
+    fn spawn_listener(
+        addr: SocketAddr,
+        next_req_num: Arc<AtomicU64>,
+        pending_conn: PendingConn,
+        cleanup_tx: UnboundedSender<u64>,
+        resp_tx: Sender<Noun>,
+    ) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let server = match Server::try_bind(&addr) {
+                Ok(server) => server,
+                Err(err) => {
+                    warn!(target: "io-drivers:http:server", "failed to bind listener on {}: {}", addr, err);
+                    let failed = Cell::from([
+                        Atom::from("listen-failed").into_rc_noun(),
+                        Atom::from(addr.to_string()).into_rc_noun(),
+                    ])
+                    .into_noun();
+                    if resp_tx.send(failed).await.is_err() {
+                        warn!(target: "io-drivers:http:server", "failed to send listen-failed event for {} to stdout task", addr);
+                    }
+                    return;
+                }
+            };
+
+            let make_svc = make_service_fn(move |_conn| {
+                let next_req_num = next_req_num.clone();
+                let pending_conn = pending_conn.clone();
+                let cleanup_tx = cleanup_tx.clone();
+                let resp_tx = resp_tx.clone();
+                async move {
+                    Ok::<_, Infallible>(service_fn(move |req| {
+                        Self::handle_conn(
+                            req,
+                            next_req_num.clone(),
+                            pending_conn.clone(),
+                            cleanup_tx.clone(),
+                            resp_tx.clone(),
+                        )
+                    }))
+                }
+            });
+
+            info!(target: "io-drivers:http:server", "listening on {}", addr);
+            if let Err(err) = server.serve(make_svc).await {
+                warn!(target: "io-drivers:http:server", "server on {} failed: {}", addr, err);
+            }
+        })
+    }
+
+    /// Converts an inbound connection into a request noun, forwards it to Arvo over
+    /// `resp_tx`, and waits for the matching reply before completing the connection.
+    async fn handle_conn(
+        req: HyperRequest<Body>,
+        next_req_num: Arc<AtomicU64>,
+        pending_conn: PendingConn,
+        cleanup_tx: UnboundedSender<u64>,
+        resp_tx: Sender<Noun>,
+    ) -> Result<HyperResponse<Body>, Infallible> {
+        let req_num = next_req_num.fetch_add(1, Ordering::Relaxed);
+        debug!(target: "io-drivers:http:server", "accepted connection as request #{}", req_num);
+
+        let (parts, body) = req.into_parts();
+        let body = match body::to_bytes(body).await {
+            Ok(body) => body,
+            Err(err) => {
+                warn!(target: "io-drivers:http:server", "failed to receive body of request #{}: {}", req_num, err);
+                return Ok(bad_gateway());
+            }
+        };
+
+        let req = Request {
+            req_num,
+            method: parts.method,
+            uri: parts.uri,
+            headers: parts.headers,
+            body,
+        };
+
+        let (reply_tx, reply_rx) = oneshot::channel();
+        pending_conn.lock().await.insert(req_num, reply_tx);
+        // Guards the entry above: dropped at the end of this function no matter which branch
+        // returns, and dropped early if hyper cancels this whole future instead.
+        let _guard = PendingConnGuard {
+            req_num,
+            cleanup_tx: cleanup_tx.clone(),
+        };
+
+        if resp_tx.send(req.into_noun()).await.is_err() {
+            warn!(target: "io-drivers:http:server", "failed to forward request #{} to Arvo", req_num);
+            return Ok(bad_gateway());
+        }
+
+        match tokio::time::timeout(RESPONSE_TIMEOUT, reply_rx).await {
+            Ok(Ok(resp)) => {
+                info!(target: "io-drivers:http:server", "sending response to request #{}", req_num);
+                let mut builder = HyperResponse::builder().status(resp.status);
+                if let Some(headers) = builder.headers_mut() {
+                    *headers = resp.headers;
+                }
+                Ok(builder.body(Body::from(resp.body)).unwrap_or_else(|_| bad_gateway()))
+            }
+            Ok(Err(_)) => {
+                warn!(target: "io-drivers:http:server", "Arvo dropped the reply channel for request #{}", req_num);
+                Ok(bad_gateway())
+            }
+            Err(_) => {
+                warn!(target: "io-drivers:http:server", "request #{} timed out waiting {:?} for Arvo's reply", req_num, RESPONSE_TIMEOUT);
+                Ok(bad_gateway())
+            }
+        }
+    }
+}
+
+/// A generic "something went wrong upstream" response for the cases above that can't
+/// otherwise produce a `HyperResponse`.
+fn bad_gateway() -> HyperResponse<Body> {
+    HyperResponse::builder()
+        .status(StatusCode::BAD_GATEWAY)
+        .body(Body::empty())
+        .expect("build response")
+}
+
+impl Driver for HttpServer {
+    fn new() -> Self {
+        let (cleanup_tx, cleanup_rx) = mpsc::unbounded_channel();
+        debug!(target: "io-drivers:http:server", "initialized driver");
+        Self {
+            next_req_num: Arc::new(AtomicU64::new(0)),
+            pending_conn: Arc::new(Mutex::new(HashMap::new())),
+            cleanup_tx,
+            cleanup_rx: Some(cleanup_rx),
+        }
+    }
+
+    fn run(mut self, mut req_rx: Receiver<Noun>, resp_tx: Sender<Noun>) -> JoinHandle<Status> {
+        let mut cleanup_rx = self.cleanup_rx.take().expect("cleanup channel");
+        let task = tokio::spawn(async move {
+            let Self {
+                next_req_num,
+                pending_conn,
+                cleanup_tx,
+                ..
+            } = self;
+            let mut listener: Option<JoinHandle<()>> = None;
+
+            loop {
+                tokio::select! {
+                    req = req_rx.recv() => {
+                        let req = match req {
+                            Some(req) => req,
+                            None => break,
+                        };
+                        if let Noun::Cell(req) = req {
+                            let (tag, req) = req.into_parts();
+                            if let Noun::Atom(tag) = &*tag {
+                                if tag == "listen" {
+                                    match Listen::try_from_noun(req) {
+                                        Ok(listen) => {
+                                            if let Some(listener) = listener.take() {
+                                                listener.abort();
+                                            }
+                                            listener = Some(Self::spawn_listener(
+                                                listen.addr,
+                                                next_req_num.clone(),
+                                                pending_conn.clone(),
+                                                cleanup_tx.clone(),
+                                                resp_tx.clone(),
+                                            ));
+                                        }
+                                        Err(err) => {
+                                            warn!(target: "io-drivers:http:server", "failed to convert listen noun: {}", err);
+                                        }
+                                    }
+                                } else if tag == "respond" {
+                                    match Response::try_from_noun(req) {
+                                        Ok(resp) => {
+                                            let req_num = resp.req_num;
+                                            if let Some(reply_tx) = pending_conn.lock().await.remove(&req_num) {
+                                                if reply_tx.send(resp).is_err() {
+                                                    warn!(target: "io-drivers:http:server", "connection for request #{} is no longer waiting for a response", req_num);
+                                                }
+                                            } else {
+                                                warn!(target: "io-drivers:http:server", "no pending connection for request #{}", req_num);
+                                            }
+                                        }
+                                        Err(err) => {
+                                            warn!(target: "io-drivers:http:server", "failed to convert response noun: {}", err);
+                                        }
+                                    }
+                                } else if let Ok(tag) = tag.as_str() {
+                                    warn!(target: "io-drivers:http:server", "ignoring request with unknown tag %{}", tag);
+                                } else {
+                                    warn!(target: "io-drivers:http:server", "ignoring request with unknown tag");
+                                }
+                            } else {
+                                warn!(target: "io-drivers:http:server", "ignoring request because the tag is a cell");
+                            }
+                        } else {
+                            warn!(target: "io-drivers:http:server", "ignoring request because it's an atom");
+                        }
+                    }
+                    Some(req_num) = cleanup_rx.recv() => {
+                        if pending_conn.lock().await.remove(&req_num).is_some() {
+                            debug!(target: "io-drivers:http:server", "evicted stale pending connection for request #{}", req_num);
+                        }
+                    }
+                }
+            }
+
+            if let Some(listener) = listener {
+                listener.abort();
+            }
+            Status::Success
+        });
+        debug!(target: "io-drivers:http:server", "spawned task");
+        task
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn request_into_noun() {
+        let req_num = 42u64;
+        let body = Bytes::from("hello");
+        let mut headers = HeaderMap::new();
+        headers.insert("x-test", HeaderValue::from_static("value"));
+
+        let req = Request {
+            req_num,
+            method: Method::POST,
+            uri: "/path".parse().expect("parse uri"),
+            headers,
+            body: body.clone(),
+        };
+
+        let noun = req.into_noun();
+        let expected = Cell::from([
+            Atom::from(req_num).into_noun(),
+            Atom::from("POST").into_noun(),
+            Atom::from("/path").into_noun(),
+            Cell::from([
+                Cell::from([Atom::from("x-test"), Atom::from("value")]).into_noun(),
+                Atom::null().into_noun(),
+            ])
+            .into_noun(),
+            Cell::from([
+                Atom::null(),
+                Cell::from([Atom::from(body.len()), Atom::from(body.to_vec())]).into_noun(),
+            ])
+            .into_noun(),
+        ])
+        .into_noun();
+
+        assert_eq!(noun, expected);
+    }
+
+    #[test]
+    fn response_try_from_noun() {
+        let noun = Cell::from([
+            Atom::from(7u64).into_noun(),
+            Atom::from(204u16).into_noun(),
+            Cell::from([
+                Cell::from([Atom::from("x-test"), Atom::from("value")]).into_noun(),
+                Atom::null().into_noun(),
+            ])
+            .into_noun(),
+            Atom::null().into_noun(),
+        ])
+        .into_rc_noun();
+
+        let resp = Response::try_from_noun(noun).expect("try_from_noun");
+        assert_eq!(resp.req_num, 7);
+        assert_eq!(resp.status, StatusCode::NO_CONTENT);
+        assert_eq!(resp.headers.get("x-test").expect("header present"), "value");
+        assert!(resp.body.is_empty());
+    }
+}
@@ -1,4 +1,5 @@
 mod http;
+mod ws;
 
 use std::marker::Unpin;
 use tokio::{
@@ -14,8 +15,19 @@ type RequestTag = u8;
 /// Tag identifying the type of a serialized IO request.
 const HTTP_CLIENT: RequestTag = 0;
 
+/// Tag identifying the type of a serialized IO request.
+const HTTP_SERVER: RequestTag = 1;
+
+/// Tag identifying the type of a serialized IO request.
+const WS_CLIENT: RequestTag = 2;
+
 /// Read incoming IO requests from some input source.
-async fn recv_io_requests(mut reader: impl AsyncReadExt + Unpin, http_client_tx: Sender<Vec<u8>>) {
+async fn recv_io_requests(
+    mut reader: impl AsyncReadExt + Unpin,
+    http_client_tx: Sender<Vec<u8>>,
+    http_server_tx: Sender<Vec<u8>>,
+    ws_client_tx: Sender<Vec<u8>>,
+) {
     loop {
         let req_len = match reader.read_u64_le().await {
             Ok(0) => break,
@@ -38,6 +50,14 @@ async fn recv_io_requests(mut reader: impl AsyncReadExt + Unpin, http_client_tx:
                     // TODO: better error handling.
                     http_client_tx.send(req).await.unwrap();
                 }
+                HTTP_SERVER => {
+                    // TODO: better error handling.
+                    http_server_tx.send(req).await.unwrap();
+                }
+                WS_CLIENT => {
+                    // TODO: better error handling.
+                    ws_client_tx.send(req).await.unwrap();
+                }
                 _ => todo!(),
             },
             Err(_) => todo!("handle error"),
@@ -73,13 +93,28 @@ pub async fn run() {
 
     // scheduling task -> http client driver task
     let (http_client_tx, http_client_rx): Channel<Vec<u8>> = mpsc::channel(QUEUE_SIZE);
-    let http_client_task = tokio::spawn(http::client::run(http_client_rx, resp_tx));
+    let http_client_task = tokio::spawn(http::client::run(http_client_rx, resp_tx.clone()));
+
+    // scheduling task -> http server driver task
+    let (http_server_tx, http_server_rx): Channel<Vec<u8>> = mpsc::channel(QUEUE_SIZE);
+    let http_server_task = tokio::spawn(http::server::run(http_server_rx, resp_tx.clone()));
+
+    // scheduling task -> websocket client driver task
+    let (ws_client_tx, ws_client_rx): Channel<Vec<u8>> = mpsc::channel(QUEUE_SIZE);
+    let ws_client_task = tokio::spawn(ws::client::run(ws_client_rx, resp_tx));
 
     // input task -> scheduling task
-    let input_task = tokio::spawn(recv_io_requests(io::stdin(), http_client_tx));
+    let input_task = tokio::spawn(recv_io_requests(
+        io::stdin(),
+        http_client_tx,
+        http_server_tx,
+        ws_client_tx,
+    ));
 
     input_task.await.unwrap();
     http_client_task.await.unwrap();
+    http_server_task.await.unwrap();
+    ws_client_task.await.unwrap();
     output_task.await.unwrap();
 }
 
@@ -108,8 +143,10 @@ mod tests {
             ];
             let reader = BufReader::new(&REQ[..]);
             let (req_tx, mut req_rx): (Sender<Vec<u8>>, Receiver<Vec<u8>>) = mpsc::channel(8);
+            let (other_tx, _other_rx): (Sender<Vec<u8>>, Receiver<Vec<u8>>) = mpsc::channel(8);
+            let (other_tx2, _other_rx2): (Sender<Vec<u8>>, Receiver<Vec<u8>>) = mpsc::channel(8);
 
-            tokio::spawn(super::recv_io_requests(reader, req_tx));
+            tokio::spawn(super::recv_io_requests(reader, req_tx, other_tx, other_tx2));
 
             let req = req_rx.recv().await.unwrap();
             let req = String::from_utf8(req).unwrap();
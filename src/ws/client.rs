@@ -0,0 +1,456 @@
+use crate::{Driver, Status};
+use futures_util::{SinkExt, StreamExt};
+use log::{debug, info, warn};
+use noun::{
+    atom::Atom,
+    cell::Cell,
+    convert::{self, IntoNoun, TryFromNoun},
+    Noun, Rc,
+};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+use tokio::{
+    sync::mpsc::{self, Receiver, Sender, UnboundedReceiver, UnboundedSender},
+    task::JoinHandle,
+};
+use tokio_tungstenite::{
+    connect_async,
+    tungstenite::{client::IntoClientRequest, http::HeaderValue, Message},
+};
+
+fn atom_as_str(atom: &Atom) -> Result<&str, convert::Error> {
+    atom.as_str().map_err(|_| convert::Error::AtomToStr)
+}
+
+/// A request to open a new WebSocket connection.
+struct Connect {
+    uri: String,
+    headers: Vec<(String, String)>,
+}
+
+impl TryFromNoun<Rc<Noun>> for Connect {
+    fn try_from_noun(req: Rc<Noun>) -> Result<Self, convert::Error> {
+        if let Noun::Cell(req) = &*req {
+            let [uri, headers] = req.as_list::<2>().ok_or(convert::Error::MissingValue)?;
+            if let (Noun::Atom(uri), mut headers) = (&*uri, headers) {
+                let uri = atom_as_str(uri)?.to_string();
+
+                let mut header_list = Vec::new();
+                while let Noun::Cell(cell) = &*headers {
+                    let header = cell.head();
+                    if let Noun::Cell(header) = &*header {
+                        if let (Noun::Atom(key), Noun::Atom(val)) =
+                            (&*header.head(), &*header.tail())
+                        {
+                            header_list
+                                .push((atom_as_str(key)?.to_string(), atom_as_str(val)?.to_string()));
+                        } else {
+                            return Err(convert::Error::UnexpectedCell);
+                        }
+                    } else {
+                        return Err(convert::Error::UnexpectedAtom);
+                    }
+                    headers = cell.tail();
+                }
+
+                Ok(Self {
+                    uri,
+                    headers: header_list,
+                })
+            } else {
+                Err(convert::Error::UnexpectedCell)
+            }
+        } else {
+            Err(convert::Error::UnexpectedCell)
+        }
+    }
+}
+
+/// A frame to send on an existing connection.
+struct SendFrame {
+    conn_id: u64,
+    message: Message,
+}
+
+impl TryFromNoun<Rc<Noun>> for SendFrame {
+    fn try_from_noun(req: Rc<Noun>) -> Result<Self, convert::Error> {
+        if let Noun::Cell(req) = &*req {
+            let [conn_id, kind, payload] =
+                req.as_list::<3>().ok_or(convert::Error::MissingValue)?;
+            if let (Noun::Atom(conn_id), Noun::Atom(kind), Noun::Atom(payload)) =
+                (&*conn_id, &*kind, &*payload)
+            {
+                let conn_id = conn_id.as_u64().ok_or(convert::Error::AtomToUint)?;
+                let message = if kind == "text" {
+                    Message::Text(atom_as_str(payload)?.to_string())
+                } else if kind == "binary" {
+                    Message::Binary(atom_as_str(payload)?.as_bytes().to_vec())
+                } else {
+                    return Err(convert::Error::ImplType);
+                };
+                Ok(Self { conn_id, message })
+            } else {
+                Err(convert::Error::UnexpectedCell)
+            }
+        } else {
+            Err(convert::Error::UnexpectedCell)
+        }
+    }
+}
+
+/// A request to close an existing connection.
+struct Close {
+    conn_id: u64,
+}
+
+impl TryFromNoun<Rc<Noun>> for Close {
+    fn try_from_noun(req: Rc<Noun>) -> Result<Self, convert::Error> {
+        if let Noun::Atom(conn_id) = &*req {
+            let conn_id = conn_id.as_u64().ok_or(convert::Error::AtomToUint)?;
+            Ok(Self { conn_id })
+        } else {
+            Err(convert::Error::UnexpectedCell)
+        }
+    }
+}
+
+/// An event delivered back to Arvo about one of the driver's connections.
+enum WsEvent {
+    /// `[%open conn_id]`, sent once the handshake completes.
+    Open { conn_id: u64 },
+    /// `[%message conn_id %text|%binary payload]`, sent once per received data frame.
+    Message { conn_id: u64, message: Message },
+    /// `[%pong conn_id]`, sent once per received pong frame.
+    Pong { conn_id: u64 },
+    /// `[%closed conn_id]`, sent once the connection ends, however it ends.
+    Closed { conn_id: u64 },
+}
+
+impl IntoNoun<Noun> for WsEvent {
+    fn into_noun(self) -> Noun {
+        let conn_id_noun = |conn_id: u64| Atom::from(conn_id).into_rc_noun();
+        match self {
+            Self::Open { conn_id } => {
+                Cell::from([Atom::from("open").into_rc_noun(), conn_id_noun(conn_id)]).into_noun()
+            }
+            Self::Message { conn_id, message } => {
+                let (kind, payload) = match message {
+                    Message::Text(text) => ("text", text.into_bytes()),
+                    Message::Binary(bytes) => ("binary", bytes),
+                    _ => unreachable!("non-data frame delivered as a message event"),
+                };
+                Cell::from([
+                    Atom::from("message").into_rc_noun(),
+                    conn_id_noun(conn_id),
+                    Atom::from(kind).into_rc_noun(),
+                    Atom::from(payload).into_rc_noun(),
+                ])
+                .into_noun()
+            }
+            Self::Pong { conn_id } => {
+                Cell::from([Atom::from("pong").into_rc_noun(), conn_id_noun(conn_id)]).into_noun()
+            }
+            Self::Closed { conn_id } => {
+                Cell::from([Atom::from("closed").into_rc_noun(), conn_id_noun(conn_id)]).into_noun()
+            }
+        }
+    }
+}
+
+/// The WebSocket client driver.
+pub struct WsClient {
+    /// Counter used to assign each connection a unique id.
+    next_conn_id: Arc<AtomicU64>,
+    /// Map from connection id to the channel used to forward outbound frames to that
+    /// connection's write half. Must only be accessed from a single task.
+    conns: HashMap<u64, UnboundedSender<Message>>,
+    /// Used by connection tasks to signal that they've closed and can be evicted from `conns`.
+    cleanup_tx: UnboundedSender<u64>,
+    /// Taken by `run` so the driver's main loop can select on it alongside `req_rx`.
+    cleanup_rx: Option<UnboundedReceiver<u64>>,
+}
+
+impl WsClient {
+    /// Opens a new connection, registering it in `conns` before the handshake even completes so
+    /// that a `send`/`close` racing the connect can still find it.
+    fn connect(&mut self, req: Rc<Noun>, resp_tx: Sender<Noun>) {
+        let connect = match Connect::try_from_noun(req) {
+            Ok(connect) => connect,
+            Err(err) => {
+                warn!(target: "io-drivers:ws:client", "failed to convert connect noun: {}", err);
+                return;
+            }
+        };
+
+        let mut request = match connect.uri.as_str().into_client_request() {
+            Ok(request) => request,
+            Err(err) => {
+                warn!(target: "io-drivers:ws:client", "failed to build connect request: {}", err);
+                return;
+            }
+        };
+        for (key, val) in &connect.headers {
+            let val = match HeaderValue::from_str(val) {
+                Ok(val) => val,
+                Err(err) => {
+                    warn!(target: "io-drivers:ws:client", "skipping invalid header value for {}: {}", key, err);
+                    continue;
+                }
+            };
+            request.headers_mut().append(
+                match key.parse::<hyper::header::HeaderName>() {
+                    Ok(key) => key,
+                    Err(err) => {
+                        warn!(target: "io-drivers:ws:client", "skipping invalid header name {}: {}", key, err);
+                        continue;
+                    }
+                },
+                val,
+            );
+        }
+
+        let conn_id = self.next_conn_id.fetch_add(1, Ordering::Relaxed);
+        debug!(target: "io-drivers:ws:client", "connecting as connection #{}", conn_id);
+
+        let (frame_tx, frame_rx) = mpsc::unbounded_channel();
+        self.conns.insert(conn_id, frame_tx);
+
+        let cleanup_tx = self.cleanup_tx.clone();
+        tokio::spawn(Self::run_conn(conn_id, request, frame_rx, resp_tx, cleanup_tx));
+    }
+
+    /// Drives a single connection: forwards outbound frames queued on `frame_rx` and delivers
+    /// inbound frames to Arvo as `WsEvent` nouns, until the connection closes either way.
+    async fn run_conn(
+        conn_id: u64,
+        request: tokio_tungstenite::tungstenite::http::Request<()>,
+        mut frame_rx: UnboundedReceiver<Message>,
+        resp_tx: Sender<Noun>,
+        cleanup_tx: UnboundedSender<u64>,
+    ) {
+        let (ws_stream, _resp) = match connect_async(request).await {
+            Ok(pair) => pair,
+            Err(err) => {
+                warn!(target: "io-drivers:ws:client", "failed to open connection #{}: {}", conn_id, err);
+                // Arvo is waiting on an open/closed event for the connect it sent; without this
+                // it would stall forever never knowing the handshake failed.
+                if resp_tx.send(WsEvent::Closed { conn_id }.into_noun()).await.is_err() {
+                    warn!(target: "io-drivers:ws:client", "failed to send closed event for connection #{} to stdout task", conn_id);
+                }
+                let _ = cleanup_tx.send(conn_id);
+                return;
+            }
+        };
+        info!(target: "io-drivers:ws:client", "opened connection #{}", conn_id);
+        let (mut write, mut read) = ws_stream.split();
+
+        if resp_tx.send(WsEvent::Open { conn_id }.into_noun()).await.is_err() {
+            warn!(target: "io-drivers:ws:client", "failed to send open event for connection #{} to stdout task", conn_id);
+        }
+
+        loop {
+            tokio::select! {
+                frame = frame_rx.recv() => {
+                    match frame {
+                        Some(message) => {
+                            if let Err(err) = write.send(message).await {
+                                warn!(target: "io-drivers:ws:client", "failed to send frame on connection #{}: {}", conn_id, err);
+                                break;
+                            }
+                        }
+                        None => {
+                            let _ = write.close().await;
+                            break;
+                        }
+                    }
+                }
+                msg = read.next() => {
+                    match msg {
+                        Some(Ok(Message::Ping(_))) => {
+                            // tungstenite replies to pings automatically; nothing to forward.
+                        }
+                        Some(Ok(Message::Pong(_))) => {
+                            if resp_tx.send(WsEvent::Pong { conn_id }.into_noun()).await.is_err() {
+                                break;
+                            }
+                        }
+                        Some(Ok(Message::Close(_))) | None => break,
+                        Some(Ok(message)) => {
+                            if resp_tx.send(WsEvent::Message { conn_id, message }.into_noun()).await.is_err() {
+                                warn!(target: "io-drivers:ws:client", "failed to send message event for connection #{} to stdout task", conn_id);
+                                break;
+                            }
+                        }
+                        Some(Err(err)) => {
+                            warn!(target: "io-drivers:ws:client", "connection #{} errored: {}", conn_id, err);
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+
+        if resp_tx.send(WsEvent::Closed { conn_id }.into_noun()).await.is_err() {
+            warn!(target: "io-drivers:ws:client", "failed to send closed event for connection #{} to stdout task", conn_id);
+        }
+        info!(target: "io-drivers:ws:client", "closed connection #{}", conn_id);
+        let _ = cleanup_tx.send(conn_id);
+    }
+
+    /// Queues a frame to be sent on an existing connection.
+    fn send_frame(&mut self, req: Rc<Noun>) {
+        let frame = match SendFrame::try_from_noun(req) {
+            Ok(frame) => frame,
+            Err(err) => {
+                warn!(target: "io-drivers:ws:client", "failed to convert send noun: {}", err);
+                return;
+            }
+        };
+        if let Some(frame_tx) = self.conns.get(&frame.conn_id) {
+            if frame_tx.send(frame.message).is_err() {
+                warn!(target: "io-drivers:ws:client", "connection #{} is no longer accepting frames", frame.conn_id);
+            }
+        } else {
+            warn!(target: "io-drivers:ws:client", "no connection #{} found in connection cache", frame.conn_id);
+        }
+    }
+
+    /// Closes an existing connection.
+    fn close(&mut self, req: Rc<Noun>) {
+        let close = match Close::try_from_noun(req) {
+            Ok(close) => close,
+            Err(err) => {
+                warn!(target: "io-drivers:ws:client", "failed to convert close noun: {}", err);
+                return;
+            }
+        };
+        if self.conns.remove(&close.conn_id).is_some() {
+            info!(target: "io-drivers:ws:client", "closing connection #{}", close.conn_id);
+        } else {
+            warn!(target: "io-drivers:ws:client", "no connection #{} found in connection cache", close.conn_id);
+        }
+    }
+}
+
+impl Driver for WsClient {
+    fn new() -> Self {
+        let (cleanup_tx, cleanup_rx) = mpsc::unbounded_channel();
+        debug!(target: "io-drivers:ws:client", "initialized driver");
+        Self {
+            next_conn_id: Arc::new(AtomicU64::new(0)),
+            conns: HashMap::new(),
+            cleanup_tx,
+            cleanup_rx: Some(cleanup_rx),
+        }
+    }
+
+    fn run(mut self, mut req_rx: Receiver<Noun>, resp_tx: Sender<Noun>) -> JoinHandle<Status> {
+        let mut cleanup_rx = self.cleanup_rx.take().expect("cleanup channel");
+        let task = tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    req = req_rx.recv() => {
+                        let req = match req {
+                            Some(req) => req,
+                            None => break,
+                        };
+                        if let Noun::Cell(req) = req {
+                            let (tag, req) = req.into_parts();
+                            if let Noun::Atom(tag) = &*tag {
+                                if tag == "connect" {
+                                    self.connect(req, resp_tx.clone());
+                                } else if tag == "send" {
+                                    self.send_frame(req);
+                                } else if tag == "close" {
+                                    self.close(req);
+                                } else {
+                                    if let Ok(tag) = tag.as_str() {
+                                        warn!(target: "io-drivers:ws:client", "ignoring request with unknown tag %{}", tag);
+                                    } else {
+                                        warn!(target: "io-drivers:ws:client", "ignoring request with unknown tag");
+                                    }
+                                }
+                            } else {
+                                warn!(target: "io-drivers:ws:client", "ignoring request because the tag is a cell");
+                            }
+                        } else {
+                            warn!(target: "io-drivers:ws:client", "ignoring request because it's an atom");
+                        }
+                    }
+                    Some(conn_id) = cleanup_rx.recv() => {
+                        if self.conns.remove(&conn_id).is_some() {
+                            debug!(target: "io-drivers:ws:client", "evicted closed connection #{} from connection cache", conn_id);
+                        }
+                    }
+                }
+            }
+            Status::Success
+        });
+        debug!(target: "io-drivers:ws:client", "spawned task");
+        task
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ws_event_into_noun() {
+        let conn_id = 3u64;
+
+        let open = WsEvent::Open { conn_id };
+        assert_eq!(
+            open.into_noun(),
+            Cell::from([Atom::from("open").into_noun(), Atom::from(conn_id).into_noun()]).into_noun()
+        );
+
+        let text = WsEvent::Message {
+            conn_id,
+            message: Message::Text("hi".to_string()),
+        };
+        assert_eq!(
+            text.into_noun(),
+            Cell::from([
+                Atom::from("message").into_noun(),
+                Atom::from(conn_id).into_noun(),
+                Atom::from("text").into_noun(),
+                Atom::from("hi").into_noun(),
+            ])
+            .into_noun()
+        );
+
+        let binary = WsEvent::Message {
+            conn_id,
+            message: Message::Binary(vec![1, 2, 3]),
+        };
+        assert_eq!(
+            binary.into_noun(),
+            Cell::from([
+                Atom::from("message").into_noun(),
+                Atom::from(conn_id).into_noun(),
+                Atom::from("binary").into_noun(),
+                Atom::from(vec![1u8, 2, 3]).into_noun(),
+            ])
+            .into_noun()
+        );
+
+        let pong = WsEvent::Pong { conn_id };
+        assert_eq!(
+            pong.into_noun(),
+            Cell::from([Atom::from("pong").into_noun(), Atom::from(conn_id).into_noun()]).into_noun()
+        );
+
+        let closed = WsEvent::Closed { conn_id };
+        assert_eq!(
+            closed.into_noun(),
+            Cell::from([Atom::from("closed").into_noun(), Atom::from(conn_id).into_noun()])
+                .into_noun()
+        );
+    }
+}